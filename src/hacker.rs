@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use thiserror::Error;
 
 #[derive(Debug, Clone)]
@@ -17,6 +19,26 @@ pub enum FilterError {
     InvalidCorrectness(String, usize),
 }
 
+#[derive(Debug, Error)]
+pub enum AnswerError {
+    #[error("the password cannot be deduced yet - {0} candidates remain.")]
+    NotYetKnown(usize),
+}
+
+#[derive(Debug, Error)]
+pub enum AddError {
+    #[error("\"{0}\" is already in the list of available passwords.")]
+    AlreadyPresent(String),
+}
+
+#[derive(Debug, Error)]
+pub enum RemoveError {
+    #[error("\"{0}\" is not in the list of available passwords.")]
+    UnknownPassword(String),
+    #[error("cannot remove the last remaining candidate password.")]
+    Impossible,
+}
+
 impl Hacker {
     /// Creates a new hacker given a list of candidate passwords.
     /// The list must be nonempty - returns `None` if the list is empty.
@@ -59,6 +81,35 @@ impl Hacker {
         }
     }
 
+    /// Adds a new candidate password. In case of error, does nothing and
+    /// returns that error.
+    pub fn add(&mut self, password: String) -> Result<(), AddError> {
+        match self.passwords.binary_search(&password) {
+            Ok(_) => Err(AddError::AlreadyPresent(password)),
+            Err(index) => {
+                self.passwords.insert(index, password);
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes a candidate password. In case of error, does nothing and
+    /// returns that error.
+    pub fn remove(&mut self, password: &str) -> Result<(), RemoveError> {
+        if self.passwords.len() == 1 {
+            // A removal shouldn't filter out *all* remaining passwords.
+            return Err(RemoveError::Impossible);
+        }
+
+        match self.passwords.binary_search_by(|pw| pw.as_str().cmp(password)) {
+            Ok(index) => {
+                self.passwords.remove(index);
+                Ok(())
+            }
+            Err(_) => Err(RemoveError::UnknownPassword(password.to_owned())),
+        }
+    }
+
     /// If the hacker knows the correct password (ie if there is only one candidate left), returns it.
     /// Otherwise, returns `None`.
     pub fn answer(&self) -> Option<&str> {
@@ -71,7 +122,7 @@ impl Hacker {
     }
 
     /// Get a list of all strings that could be the password.
-    pub fn candidates(&self) -> impl Iterator<Item = &str> {
+    pub fn candidates(&self) -> impl ExactSizeIterator<Item = &str> {
         self.passwords.iter().map(|pw: &String| pw.as_ref())
     }
 
@@ -98,13 +149,124 @@ impl Hacker {
             .min_by_key(|s| filtration_power(s))
             .expect("Set of candidates cannot be empty.")
     }
+
+    /// Recommend the next password to guess, maximizing Shannon information gain.
+    ///
+    /// For a guess `g`, bucket the candidates by their correctness against
+    /// `g` and compute the entropy of that distribution. A guess that
+    /// splits the pool into many evenly-sized buckets carries more
+    /// information than one with the same expected remaining-pool size but
+    /// lopsided buckets, which `recommend` cannot distinguish. Guesses are
+    /// drawn from the remaining candidates, so the tie-break of preferring
+    /// a guess that is still a candidate is automatically satisfied.
+    pub fn recommend_by_entropy(&self) -> &str {
+        let n = self.passwords.len();
+        if n == 1 {
+            return &self.passwords[0];
+        }
+
+        let entropy = |guess: &str| -> f64 {
+            let n = n as f64;
+            (0..=guess.chars().count())
+                .filter_map(|correctness| {
+                    let count = self
+                        .candidates()
+                        .filter(|candidate| commonality(candidate, guess) == correctness)
+                        .count();
+                    if count == 0 {
+                        None
+                    } else {
+                        let p = count as f64 / n;
+                        Some(-p * p.log2())
+                    }
+                })
+                .sum()
+        };
+
+        self.candidates()
+            .max_by(|a, b| {
+                entropy(a)
+                    .partial_cmp(&entropy(b))
+                    .expect("entropy is a finite sum of finite terms")
+            })
+            .expect("Set of candidates cannot be empty.")
+    }
+
+    /// Returns the worst-case number of guesses needed to narrow the current
+    /// candidates down to a single password, assuming every guess is chosen
+    /// optimally. The candidate set is solvable within `tries` guesses
+    /// (matching the real minigame's "N guesses before lockout" budget) if
+    /// and only if this is strictly less than `tries`.
+    pub fn worst_case_depth(&self) -> usize {
+        worst_case_depth(&self.passwords, &mut HashMap::new())
+    }
 }
 
 /// Returns the number of characters one string has in common with another.
 /// For a character to be common to both strings, it must appear in the same place.
 fn commonality(s: &str, t: &str) -> usize {
-    s.chars()
-        .zip(t.chars())
-        .map(|(sc, tc)| usize::from(sc == tc))
-        .sum()
+    char_match_mask(s, t).filter(|&matches| matches).count()
+}
+
+/// Returns, for each character position, whether `s` and `t` agree there.
+/// `commonality` is just the count of `true` entries yielded here; this
+/// exists separately so callers (e.g. the CLI's colorized output) can show
+/// *which* positions matched, not just how many.
+pub fn char_match_mask<'a>(s: &'a str, t: &'a str) -> impl Iterator<Item = bool> + 'a {
+    s.chars().zip(t.chars()).map(|(sc, tc)| sc == tc)
+}
+
+/// Recursively computes the worst-case depth to narrow `candidates` down to
+/// a single password: if one candidate remains, the depth is `0`; otherwise,
+/// for every possible guess, partition the candidates by the correctness
+/// that guess would yield, and take the maximum depth over all buckets plus
+/// one. The result is the minimum of that over all guesses.
+///
+/// Memoized on the candidate subset, which is always sorted (since it's
+/// derived from the sorted `passwords` list by filtering). Guesses are
+/// pruned as soon as one bucket alone makes the guess at least as deep as
+/// the best guess found so far, since no later bucket can make that guess
+/// better. A
+/// guess whose largest bucket contains every candidate is skipped outright:
+/// it makes no progress (candidates can have different lengths, so e.g.
+/// guessing "AB" against `["AB", "ABC", "ABD"]` puts all three in the same
+/// bucket), and recursing on it would just repeat this exact call forever.
+fn worst_case_depth(candidates: &[String], memo: &mut HashMap<Vec<String>, usize>) -> usize {
+    if candidates.len() <= 1 {
+        return 0;
+    }
+    if let Some(&depth) = memo.get(candidates) {
+        return depth;
+    }
+
+    let mut best = usize::MAX;
+    for guess in candidates {
+        let mut buckets: Vec<Vec<String>> = vec![Vec::new(); guess.chars().count() + 1];
+        for candidate in candidates {
+            buckets[commonality(candidate, guess)].push(candidate.clone());
+        }
+
+        let mut worst_bucket_depth = 0;
+        let mut pruned = false;
+        for bucket in buckets.iter().filter(|bucket| !bucket.is_empty()) {
+            if bucket.len() == candidates.len() {
+                pruned = true;
+                break;
+            }
+
+            worst_bucket_depth = worst_bucket_depth.max(worst_case_depth(bucket, memo));
+            if worst_bucket_depth + 1 >= best {
+                // This guess already can't beat the best guess found so far.
+                pruned = true;
+                break;
+            }
+        }
+
+        if !pruned {
+            best = worst_bucket_depth + 1;
+        }
+    }
+
+    memo.insert(candidates.to_vec(), best);
+    best
 }
@@ -1,4 +1,6 @@
 pub mod hacker;
+pub mod mask;
+pub mod parser;
 pub mod user;
 
 pub use hacker::Hacker;
@@ -7,10 +9,23 @@ pub use user::{TextStreamUser, User};
 pub struct App<U> {
     pub hacker: Hacker,
     pub user: U,
+    /// The most recent successful guess, used to highlight matched
+    /// characters when showing remaining candidates.
+    last_guess: Option<String>,
 }
 
 struct Terminate(bool);
 
+impl<U> App<U> {
+    pub fn new(hacker: Hacker, user: U) -> Self {
+        Self {
+            hacker,
+            user,
+            last_guess: None,
+        }
+    }
+}
+
 impl<U> App<U>
 where
     U: User,
@@ -31,27 +46,43 @@ where
         match command {
             Command::Exit => return Ok(Terminate(true)),
             Command::SeePasswords => {
-                self.user.show_passwords(self.hacker.candidates())?;
+                self.user
+                    .show_passwords(self.hacker.candidates(), self.last_guess.as_deref())?;
             }
-            Command::SeeRecommended => {
-                if let Err(e) = self.hacker.recommend() {
-                    self.user.show_error(e)?;
-                }
+            Command::SeeRecommended { entropy } => {
+                let recommended = if entropy {
+                    self.hacker.recommend_by_entropy()
+                } else {
+                    self.hacker.recommend()
+                };
+                self.user.show_recommended(recommended)?;
             }
-            Command::SeeAnswer => {}
+            Command::SeeAnswer => match self.hacker.answer() {
+                Some(answer) => self.user.show_answer(answer)?,
+                None => {
+                    let remaining = self.hacker.candidates().count();
+                    self.user
+                        .show_error(hacker::AnswerError::NotYetKnown(remaining))?;
+                }
+            },
             Command::FilterPasswords { guess, correctness } => {
-                if let Err(e) = self.hacker.filter(&guess, correctness) {
-                    self.user.show_error(e)?;
+                match self.hacker.filter(&guess, correctness) {
+                    Ok(()) => self.last_guess = Some(guess),
+                    Err(e) => self.user.show_error(e)?,
                 }
             }
-            Command::AddPassword(pw) => {
-                if let Err(e) = self.hacker.add(pw) {
-                    self.user.show_error(e)?;
+            Command::AddPassword(pws) => {
+                for pw in pws {
+                    if let Err(e) = self.hacker.add(pw) {
+                        self.user.show_error(e)?;
+                    }
                 }
             }
-            Command::RemovePassword(pw) => {
-                if let Err(e) = self.hacker.remove(pw.as_str()) {
-                    self.user.show_error(e)?;
+            Command::RemovePassword(pws) => {
+                for pw in pws {
+                    if let Err(e) = self.hacker.remove(pw.as_str()) {
+                        self.user.show_error(e)?;
+                    }
                 }
             }
             Command::Help => {
@@ -1,12 +1,49 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
 use structopt::StructOpt;
 
 use robco::hacker::Hacker;
+use robco::mask;
 use robco::user::TextStreamUser;
 use robco::App;
 
 #[derive(Debug, StructOpt)]
 struct Opt {
     passwords: Vec<String>,
+
+    /// Build (additional) candidates from a placeholder mask: `?u`/`?l`/`?d`/`?s`
+    /// for upper/lower/digit/symbol, or `?1`..`?9` for custom charsets given
+    /// with `--charset`. Literal characters pass through unchanged.
+    #[structopt(long)]
+    mask: Option<String>,
+
+    /// Custom charset for the numbered placeholders `?1`..`?9` in `--mask`,
+    /// in order. May be repeated.
+    #[structopt(short = "c", long = "charset")]
+    charset: Vec<String>,
+
+    /// Upper bound on the number of candidates `--mask` may expand to.
+    #[structopt(long, default_value = "1000000")]
+    mask_cap: usize,
+
+    /// Read (additional) candidates, one per line, from a file, or from
+    /// stdin if the path is `-`. Blank lines are skipped and surrounding
+    /// whitespace is trimmed.
+    #[structopt(long)]
+    wordlist: Option<PathBuf>,
+
+    /// Instead of an interactive session, report whether the password is
+    /// guaranteed deducible within a fixed number of guesses (see `--tries`),
+    /// and what to guess first if so.
+    #[structopt(long)]
+    solve: bool,
+
+    /// Guess budget for `--solve`. The real minigame allows four guesses
+    /// before lockout.
+    #[structopt(long, default_value = "4")]
+    tries: usize,
 }
 
 fn main() {
@@ -17,9 +54,65 @@ fn main() {
 }
 
 fn run(opt: Opt) -> anyhow::Result<()> {
-    let hacker = Hacker::new(opt.passwords);
+    let mut passwords = opt.passwords;
+    if let Some(mask) = &opt.mask {
+        passwords.extend(mask::expand(mask, &opt.charset, opt.mask_cap)?);
+    }
+    if let Some(path) = &opt.wordlist {
+        passwords.extend(read_wordlist(path)?);
+    }
+
+    let hacker = Hacker::new(passwords).ok_or_else(|| {
+        anyhow::anyhow!("no candidate passwords given (via positional arguments, --mask, or --wordlist)")
+    })?;
+
+    if opt.solve {
+        return solve(&hacker, opt.tries);
+    }
+
     let user = TextStreamUser::std();
-    let mut app = App { hacker, user };
+    let mut app = App::new(hacker, user);
     app.run()?;
     Ok(())
 }
+
+/// Reports whether `hacker`'s current candidates are guaranteed solvable
+/// within `tries` guesses in the worst case, and the best guess to start
+/// with if so.
+fn solve(hacker: &Hacker, tries: usize) -> anyhow::Result<()> {
+    let depth = hacker.worst_case_depth();
+    if depth < tries {
+        println!(
+            "Solvable within {} tries (worst case needs {}). Recommended first guess: {}",
+            tries,
+            depth,
+            hacker.recommend()
+        );
+    } else {
+        println!(
+            "Not guaranteed solvable within {} tries (worst case needs {}).",
+            tries, depth
+        );
+    }
+    Ok(())
+}
+
+/// Reads one candidate password per line from `path`, or from stdin if
+/// `path` is `-`. Blank lines are skipped and surrounding whitespace is
+/// trimmed.
+fn read_wordlist(path: &Path) -> anyhow::Result<Vec<String>> {
+    let input: Box<dyn BufRead> = if path == Path::new("-") {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(File::open(path)?))
+    };
+
+    let mut passwords = Vec::new();
+    for line in input.lines() {
+        let trimmed = line?.trim().to_owned();
+        if !trimmed.is_empty() {
+            passwords.push(trimmed);
+        }
+    }
+    Ok(passwords)
+}
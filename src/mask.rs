@@ -0,0 +1,88 @@
+use thiserror::Error;
+
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+#[derive(Debug, Error)]
+pub enum MaskError {
+    #[error("mask references custom charset ?{0}, but only {1} were given with --charset")]
+    UndefinedCharset(u8, usize),
+    #[error("custom charset ?{0} given with --charset is empty")]
+    EmptyCharset(u8),
+    #[error("\"?\" at end of mask is not followed by a placeholder letter")]
+    DanglingPlaceholder,
+    #[error("\"?{0}\" is not a recognised placeholder (expected u, l, d, s, or 1-9)")]
+    UnknownPlaceholder(char),
+    #[error("mask is empty")]
+    Empty,
+    #[error("mask expands to more than the cap of {0} candidates")]
+    TooManyCandidates(usize),
+}
+
+/// Expands a placeholder mask into every matching string, mirroring the
+/// placeholder syntax of password wordlist generators: `?u` = uppercase
+/// letter, `?l` = lowercase, `?d` = digit, `?s` = symbol, and `?1`..`?9` for
+/// custom charsets supplied in `charsets`, in order. Literal characters pass
+/// through unchanged. `cap` bounds the size of the cartesian product so a
+/// long mask can't exhaust memory.
+pub fn expand(mask: &str, charsets: &[String], cap: usize) -> Result<Vec<String>, MaskError> {
+    if mask.is_empty() {
+        // An empty mask can't stand for any password; without this a blank
+        // `--mask` would silently expand to a single empty-string candidate.
+        return Err(MaskError::Empty);
+    }
+
+    let alphabets = parse(mask, charsets)?;
+
+    let total = alphabets
+        .iter()
+        .try_fold(1usize, |acc, a| acc.checked_mul(a.len()))
+        .filter(|&total| total <= cap)
+        .ok_or(MaskError::TooManyCandidates(cap))?;
+
+    let mut candidates = Vec::with_capacity(total);
+    candidates.push(String::new());
+    for alphabet in alphabets {
+        candidates = candidates
+            .iter()
+            .flat_map(|prefix| alphabet.iter().map(move |c| format!("{}{}", prefix, c)))
+            .collect();
+    }
+
+    Ok(candidates)
+}
+
+/// Parses `mask` into one alphabet per output character.
+fn parse(mask: &str, charsets: &[String]) -> Result<Vec<Vec<char>>, MaskError> {
+    let mut alphabets = Vec::new();
+    let mut chars = mask.chars();
+    while let Some(c) = chars.next() {
+        if c != '?' {
+            alphabets.push(vec![c]);
+            continue;
+        }
+
+        let placeholder = chars.next().ok_or(MaskError::DanglingPlaceholder)?;
+        let alphabet = match placeholder {
+            'u' => UPPER.chars().collect(),
+            'l' => LOWER.chars().collect(),
+            'd' => DIGITS.chars().collect(),
+            's' => SYMBOLS.chars().collect(),
+            digit @ '1'..='9' => {
+                let index = digit.to_digit(10).expect("already matched '1'..='9'") as usize - 1;
+                let charset = charsets
+                    .get(index)
+                    .ok_or(MaskError::UndefinedCharset(digit as u8 - b'0', charsets.len()))?;
+                if charset.is_empty() {
+                    return Err(MaskError::EmptyCharset(digit as u8 - b'0'));
+                }
+                charset.chars().collect()
+            }
+            other => return Err(MaskError::UnknownPlaceholder(other)),
+        };
+        alphabets.push(alphabet);
+    }
+    Ok(alphabets)
+}
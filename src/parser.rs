@@ -0,0 +1,209 @@
+//! A small composable-parser toolkit for the command language in
+//! [`crate::user`]: a [`Cursor`] tokenizes one line at a time, and
+//! [`Parser`] combinators (`map`, `and`, `bind`, `skip`, `optional`, ...)
+//! build up whole-command grammars from those tokens, tracking byte offsets
+//! throughout so parse errors can point at the exact token that failed.
+
+use std::fmt;
+use std::rc::Rc;
+
+/// A byte range into the line being parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}", self.start + 1)
+    }
+}
+
+/// Tracks how much of a line a parser has consumed so far.
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, offset: 0 }
+    }
+
+    /// The zero-width span at the cursor's current position, for errors
+    /// about a token that should have been there but wasn't.
+    pub fn eof_span(&self) -> Span {
+        Span {
+            start: self.offset,
+            end: self.offset,
+        }
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.input = &self.input[n..];
+        self.offset += n;
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.input.trim_start();
+        let skipped = self.input.len() - trimmed.len();
+        self.advance(skipped);
+    }
+
+    /// Consumes one whitespace-delimited token and returns its (unquoted)
+    /// text and span, `None` at the end of the line, or an error if a
+    /// `"`-quoted token is never closed.
+    ///
+    /// A token is either a `"`-quoted string, which may contain spaces and
+    /// writes a literal `"` as `""`, or a bare run of non-whitespace
+    /// characters.
+    pub fn take_token(&mut self) -> Result<Option<(String, Span)>, UnterminatedQuote> {
+        self.skip_whitespace();
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+
+        let start = self.offset;
+        let text = if self.input.starts_with('"') {
+            self.take_quoted(start)?
+        } else {
+            self.take_bare()
+        };
+        Ok(Some((
+            text,
+            Span {
+                start,
+                end: self.offset,
+            },
+        )))
+    }
+
+    fn take_bare(&mut self) -> String {
+        let len = self
+            .input
+            .find(char::is_whitespace)
+            .unwrap_or(self.input.len());
+        let text = self.input[..len].to_owned();
+        self.advance(len);
+        text
+    }
+
+    fn take_quoted(&mut self, start: usize) -> Result<String, UnterminatedQuote> {
+        self.advance(1); // opening quote
+        let mut text = String::new();
+        loop {
+            match self.input.find('"') {
+                None => {
+                    return Err(UnterminatedQuote {
+                        span: Span {
+                            start,
+                            end: start + 1,
+                        },
+                    });
+                }
+                Some(i) => {
+                    text.push_str(&self.input[..i]);
+                    self.advance(i + 1); // closing quote
+                    if self.input.starts_with('"') {
+                        // `""` inside a quoted string is a literal quote.
+                        text.push('"');
+                        self.advance(1);
+                    } else {
+                        return Ok(text);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A `"` was opened but never closed, with the span of the opening quote.
+#[derive(Debug, Clone, Copy)]
+pub struct UnterminatedQuote {
+    pub span: Span,
+}
+
+/// A parser: given a [`Cursor`], either produces a `T` and the cursor
+/// advanced past what it consumed, or fails with an `E`. Small parsers for
+/// individual tokens (`token`, `literal` and `eof` in [`crate::user`]) are composed into
+/// parsers for whole commands via [`Parser::map`], [`Parser::and`],
+/// [`Parser::bind`], and friends, rather than hand-written per command.
+pub struct Parser<'a, T, E> {
+    #[allow(clippy::type_complexity)]
+    run: Rc<dyn Fn(Cursor<'a>) -> Result<(T, Cursor<'a>), E> + 'a>,
+}
+
+impl<'a, T, E> Clone for Parser<'a, T, E> {
+    fn clone(&self) -> Self {
+        Self {
+            run: Rc::clone(&self.run),
+        }
+    }
+}
+
+impl<'a, T: 'a, E: 'a> Parser<'a, T, E> {
+    pub fn new(run: impl Fn(Cursor<'a>) -> Result<(T, Cursor<'a>), E> + 'a) -> Self {
+        Self { run: Rc::new(run) }
+    }
+
+    pub fn parse(&self, cursor: Cursor<'a>) -> Result<(T, Cursor<'a>), E> {
+        (self.run)(cursor)
+    }
+
+    /// Transforms a successful result, leaving failure untouched.
+    pub fn map<U: 'a>(self, f: impl Fn(T) -> U + 'a) -> Parser<'a, U, E> {
+        Parser::new(move |cursor| {
+            let (value, cursor) = self.parse(cursor)?;
+            Ok((f(value), cursor))
+        })
+    }
+
+    /// Runs `self`, then feeds its result into `f` to pick and run the next
+    /// parser. This is how the command grammar dispatches on a command
+    /// word: `command_word().bind(|word| match word { ... })`.
+    pub fn bind<U: 'a>(self, f: impl Fn(T) -> Parser<'a, U, E> + 'a) -> Parser<'a, U, E> {
+        Parser::new(move |cursor| {
+            let (value, cursor) = self.parse(cursor)?;
+            f(value).parse(cursor)
+        })
+    }
+
+    /// Runs `self` then `next` in sequence, keeping both results.
+    pub fn and<U: 'a>(self, next: Parser<'a, U, E>) -> Parser<'a, (T, U), E> {
+        Parser::new(move |cursor| {
+            let (a, cursor) = self.parse(cursor)?;
+            let (b, cursor) = next.parse(cursor)?;
+            Ok(((a, b), cursor))
+        })
+    }
+
+    /// Runs `self` then `next` in sequence, keeping only `self`'s result.
+    /// Used to reject trailing garbage: `parser.skip(eof())`.
+    pub fn skip<U: 'a>(self, next: Parser<'a, U, E>) -> Parser<'a, T, E> {
+        Parser::new(move |cursor| {
+            let (value, cursor) = self.parse(cursor)?;
+            let (_, cursor) = next.parse(cursor)?;
+            Ok((value, cursor))
+        })
+    }
+
+    /// Maps a successful result through a fallible conversion.
+    pub fn and_then<U: 'a>(self, f: impl Fn(T) -> Result<U, E> + 'a) -> Parser<'a, U, E> {
+        Parser::new(move |cursor| {
+            let (value, cursor) = self.parse(cursor)?;
+            Ok((f(value)?, cursor))
+        })
+    }
+}
+
+/// Tries `parser`; if it fails, succeeds with `None` without consuming any
+/// input (cursors are cheap to rewind: just a borrowed `&str` and an
+/// offset).
+pub fn optional<'a, T: 'a, E: 'a>(parser: Parser<'a, T, E>) -> Parser<'a, Option<T>, E> {
+    Parser::new(move |cursor| match parser.parse(cursor) {
+        Ok((value, cursor)) => Ok((Some(value), cursor)),
+        Err(_) => Ok((None, cursor)),
+    })
+}
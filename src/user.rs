@@ -1,8 +1,11 @@
 use thiserror::Error;
 
-use std::io::{self, BufRead, BufReader, Stderr, Stdin, Stdout, Write};
+use std::io::{self, BufRead, BufReader, IsTerminal, Stderr, Stdin, Stdout, Write};
 use std::{error::Error as StdError, fmt::Display};
 
+use crate::hacker;
+use crate::parser::{self, Cursor, Parser, Span};
+
 /// Abstracts out the interface for testing and actual usage.
 pub trait User {
     type Err: 'static + Sync + Send + StdError;
@@ -10,8 +13,14 @@ pub trait User {
     /// Ask the user what they want to do.
     fn get_request(&mut self) -> Result<Command, Self::Err>;
 
-    /// Show the user all remaining passwords.
-    fn show_passwords<'a, Iter>(&mut self, passwords: Iter) -> Result<(), Self::Err>
+    /// Show the user all remaining passwords. If `last_guess` is given,
+    /// implementations may highlight the characters each candidate has in
+    /// common with it at the same position.
+    fn show_passwords<'a, Iter>(
+        &mut self,
+        passwords: Iter,
+        last_guess: Option<&str>,
+    ) -> Result<(), Self::Err>
     where
         Iter: ExactSizeIterator<Item = &'a str>;
 
@@ -32,11 +41,11 @@ pub trait User {
 pub enum Command {
     Exit,
     SeePasswords,
-    SeeRecommended,
+    SeeRecommended { entropy: bool },
     SeeAnswer,
     FilterPasswords { guess: String, correctness: usize },
-    AddPassword(String),
-    RemovePassword(String),
+    AddPassword(Vec<String>),
+    RemovePassword(Vec<String>),
     Help,
 }
 
@@ -44,6 +53,9 @@ pub struct TextStreamUser<I, O, E> {
     input: I,
     output: O,
     errput: E,
+    /// Whether to highlight matched characters in `show_passwords` with ANSI
+    /// color codes. Should be `false` when not writing to a TTY.
+    color: bool,
 }
 
 impl<I, O, E> TextStreamUser<I, O, E>
@@ -57,13 +69,21 @@ where
             input,
             output,
             errput,
+            color: false,
         }
     }
+
+    /// Enables or disables ANSI color highlighting in `show_passwords`.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
 }
 
 impl TextStreamUser<BufReader<Stdin>, Stdout, Stderr> {
     pub fn std() -> Self {
         Self::new(BufReader::new(io::stdin()), io::stdout(), io::stderr())
+            .with_color(io::stdout().is_terminal())
     }
 }
 
@@ -88,13 +108,20 @@ where
         }
     }
 
-    fn show_passwords<'a, Iter>(&mut self, passwords: Iter) -> Result<(), Self::Err>
+    fn show_passwords<'a, Iter>(
+        &mut self,
+        passwords: Iter,
+        last_guess: Option<&str>,
+    ) -> Result<(), Self::Err>
     where
         Iter: ExactSizeIterator<Item = &'a str>,
     {
         writeln!(self.errput, "Remaining candidate passwords: ({})", passwords.len())?;
         for pw in passwords {
-            writeln!(self.output, " * {}", pw)?;
+            match (self.color, last_guess) {
+                (true, Some(guess)) => writeln!(self.output, " * {}", highlight(pw, guess))?,
+                _ => writeln!(self.output, " * {}", pw)?,
+            }
         }
         writeln!(self.errput)?;
 
@@ -131,8 +158,33 @@ where
     }
 }
 
+/// Renders `candidate` with the characters it shares with `guess` at the
+/// same position highlighted green, via ANSI escape codes.
+fn highlight(candidate: &str, guess: &str) -> String {
+    hacker::char_match_mask(candidate, guess)
+        .chain(std::iter::repeat(false))
+        .zip(candidate.chars())
+        .map(|(matches, c)| {
+            if matches {
+                format!("\x1b[32m{}\x1b[0m", c)
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+/// A command-language parse error, with the span of the offending token so
+/// the user can see exactly what went wrong.
 #[derive(Debug, Error)]
-pub enum ParseError {
+#[error("{kind} ({span})")]
+pub struct ParseError {
+    span: Span,
+    kind: ParseErrorKind,
+}
+
+#[derive(Debug, Error)]
+enum ParseErrorKind {
     #[error("expected command, found blank line")]
     Blank,
     #[error("command not recognised: {0}")]
@@ -143,109 +195,130 @@ pub enum ParseError {
     MissingToken(&'static str),
     #[error("cannot parse correctness value - expected nonnegative integer, found {0} ({1})")]
     MalformedCorrectness(String, std::num::ParseIntError),
+    #[error("unterminated quoted string")]
+    UnterminatedQuote,
 }
 
-fn parse_command(line: &str) -> Result<Command, ParseError> {
-    let mut tokens = line.split_whitespace();
-    let command = tokens.next().ok_or(ParseError::Blank)?;
-    let args = tokens;
-    match command {
-        "exit" => parse_exit(args),
-        "view" => parse_view(args),
-        "recommend" => parse_recommend(args),
-        "answer" => parse_answer(args), 
-        "guess" => parse_guess(args),
-        "add" => parse_add(args),
-        "remove" => parse_remove(args),
-        "help" => parse_help(args),
-        unrecognised => Err(ParseError::UnrecognisedCommand(unrecognised.to_owned())),
-    }
+fn error(span: Span, kind: ParseErrorKind) -> ParseError {
+    ParseError { span, kind }
 }
 
-fn parse_exit<'a, I>(mut args: I) -> Result<Command, ParseError>
-where
-    I: Iterator<Item = &'a str>,
-{
-    match args.next() {
-        None => Ok(Command::Exit),
-        Some(tok) => Err(ParseError::UnexpectedToken(tok.to_owned())),
+impl From<parser::UnterminatedQuote> for ParseError {
+    fn from(e: parser::UnterminatedQuote) -> Self {
+        error(e.span, ParseErrorKind::UnterminatedQuote)
     }
 }
 
-fn parse_help<'a, I>(mut args: I) -> Result<Command, ParseError>
-where
-    I: Iterator<Item = &'a str>,
-{
-    match args.next() {
-        None => Ok(Command::Help),
-        Some(tok) => Err(ParseError::UnexpectedToken(tok.to_owned())),
-    }
+/// Consumes one token, failing with [`ParseErrorKind::UnterminatedQuote`]
+/// rather than letting [`Cursor::take_token`]'s error pass through raw.
+fn next_token(cursor: &mut Cursor) -> Result<Option<(String, Span)>, ParseError> {
+    Ok(cursor.take_token()?)
 }
 
-fn parse_view<'a, I>(mut args: I) -> Result<Command, ParseError>
-where
-    I: Iterator<Item = &'a str>,
-{
-    match args.next() {
-        None => Ok(Command::SeePasswords),
-        Some(tok) => Err(ParseError::UnexpectedToken(tok.to_owned())),
-    }
+/// A token parser, labelled `what` for the error raised if none is left.
+fn token<'a>(what: &'static str) -> Parser<'a, (String, Span), ParseError> {
+    Parser::new(move |mut cursor| match next_token(&mut cursor)? {
+        Some(t) => Ok((t, cursor)),
+        None => Err(error(cursor.eof_span(), ParseErrorKind::MissingToken(what))),
+    })
 }
 
-fn parse_answer<'a, I>(mut args: I) -> Result<Command, ParseError>
-where
-    I: Iterator<Item = &'a str>,
-{
-    match args.next() {
-        None => Ok(Command::SeeAnswer),
-        Some(tok) => Err(ParseError::UnexpectedToken(tok.to_owned())),
-    }
+/// A token parser that only succeeds if the token is exactly `word`.
+fn literal<'a>(word: &'static str) -> Parser<'a, Span, ParseError> {
+    command_word().and_then(move |(tok, span)| {
+        if tok == word {
+            Ok(span)
+        } else {
+            Err(error(span, ParseErrorKind::UnexpectedToken(tok)))
+        }
+    })
 }
 
-fn parse_guess<'a, I>(mut args: I) -> Result<Command, ParseError>
-where
-    I: Iterator<Item = &'a str>,
-{
-    let guess = args.next().ok_or(ParseError::MissingToken("guess"))?;
-    let correctness = args
-        .next()
-        .ok_or(ParseError::MissingToken("correctness"))?;
-    let correctness = correctness
-        .parse::<usize>()
-        .map_err(|e| ParseError::MalformedCorrectness(correctness.to_owned(), e))?;
-
-    Ok(Command::FilterPasswords {
-        guess: guess.to_owned(),
-        correctness,
+/// The first token of a line, or [`ParseErrorKind::Blank`] if there isn't
+/// one. Used to read the command word itself, and by [`literal`] to read a
+/// keyword argument such as `--entropy`.
+fn command_word<'a>() -> Parser<'a, (String, Span), ParseError> {
+    Parser::new(|mut cursor| match next_token(&mut cursor)? {
+        Some(t) => Ok((t, cursor)),
+        None => Err(error(cursor.eof_span(), ParseErrorKind::Blank)),
     })
 }
 
-fn parse_recommend<'a, I>(mut args: I) -> Result<Command, ParseError>
-where
-    I: Iterator<Item = &'a str>,
-{
-    match args.next() {
-        None => Ok(Command::SeeRecommended),
-        Some(tok) => Err(ParseError::UnexpectedToken(tok.to_owned())),
-    }
+/// Rejects any token left over once a command has consumed everything it
+/// understands, the same way for every command.
+fn eof<'a>() -> Parser<'a, (), ParseError> {
+    Parser::new(|mut cursor| match next_token(&mut cursor)? {
+        None => Ok(((), cursor)),
+        Some((tok, span)) => Err(error(span, ParseErrorKind::UnexpectedToken(tok))),
+    })
 }
 
-fn parse_add<'a, I>(mut args: I) -> Result<Command, ParseError>
-where
-    I: Iterator<Item = &'a str>,
-{
-    match args.next() {
-        Some(pw) => Ok(Command::AddPassword(pw.to_owned())),
-        None => Err(ParseError::MissingToken("password to add"))
-    }
+/// Consumes every remaining token as an argument, for commands like `add`
+/// and `remove` that accept one or more passwords in a single line.
+fn many1<'a>(what: &'static str) -> Parser<'a, Vec<String>, ParseError> {
+    Parser::new(move |mut cursor| {
+        let mut items = Vec::new();
+        while let Some((tok, _)) = next_token(&mut cursor)? {
+            items.push(tok);
+        }
+
+        if items.is_empty() {
+            Err(error(cursor.eof_span(), ParseErrorKind::MissingToken(what)))
+        } else {
+            Ok((items, cursor))
+        }
+    })
 }
 
-fn parse_remove<'a, I>(mut args: I) -> Result<Command, ParseError>
-where
-    I: Iterator<Item = &'a str>,
-{
-    match args.next() {
-        Some(pw) => Ok(Command::RemovePassword(pw.to_owned())),
-        None => Err(ParseError::MissingToken("password to remove"))
-    }
+fn recommend_args<'a>() -> Parser<'a, Command, ParseError> {
+    parser::optional(literal("--entropy"))
+        .map(|flag| Command::SeeRecommended {
+            entropy: flag.is_some(),
+        })
+        .skip(eof())
+}
+
+fn guess_args<'a>() -> Parser<'a, Command, ParseError> {
+    token("guess")
+        .and(token("correctness"))
+        .and_then(|((guess, _), (correctness_tok, correctness_span))| {
+            let correctness = correctness_tok.parse::<usize>().map_err(|e| {
+                error(
+                    correctness_span,
+                    ParseErrorKind::MalformedCorrectness(correctness_tok, e),
+                )
+            })?;
+            Ok((guess, correctness))
+        })
+        .map(|(guess, correctness)| Command::FilterPasswords { guess, correctness })
+        .skip(eof())
+}
+
+/// The whole command grammar, built by composing the token-level parsers
+/// above: read the command word, then dispatch on it to the parser for
+/// that command's arguments.
+fn command_parser<'a>() -> Parser<'a, Command, ParseError> {
+    command_word().bind(|(word, span)| match word.as_str() {
+        "exit" => eof().map(|_| Command::Exit),
+        "help" => eof().map(|_| Command::Help),
+        "view" => eof().map(|_| Command::SeePasswords),
+        "answer" => eof().map(|_| Command::SeeAnswer),
+        "recommend" => recommend_args(),
+        "guess" => guess_args(),
+        "add" => many1("password to add").map(Command::AddPassword),
+        "remove" => many1("password to remove").map(Command::RemovePassword),
+        word => {
+            let (word, span) = (word.to_owned(), span);
+            Parser::new(move |_| {
+                Err(error(span, ParseErrorKind::UnrecognisedCommand(word.clone())))
+            })
+        }
+    })
+}
+
+/// Parses one line of the command language.
+fn parse_command(line: &str) -> Result<Command, ParseError> {
+    command_parser()
+        .parse(Cursor::new(line))
+        .map(|(command, _)| command)
 }